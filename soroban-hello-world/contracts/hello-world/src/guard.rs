@@ -0,0 +1,35 @@
+// ----------------------------------------------------------------------
+// REENTRANCY GUARD
+// ----------------------------------------------------------------------
+// Every lifecycle call that moves money follows the same order:
+//   1. guard::enter  (job is now "in flight")
+//   2. checks (read-only token queries like decimals() count as checks)
+//   3. ALL storage writes
+//   4. calls that can act on our state: token transfers, subscriber callbacks
+//   5. guard::exit
+// create_escrow skips steps 1 and 5: its job ID comes fresh from
+// JobCounter, so nothing can already be in flight on it. It still writes
+// the counter and the job before the token or any subscriber is called.
+//
+// The Soroban host already refuses to re-enter a contract that is on the
+// call stack, so a malicious token or subscriber can't get back in today
+// and this flag never trips on-chain. It is defense in depth: it makes the
+// ordering rule explicit and keeps holding if re-entry is ever allowed.
+// The flag lives in temporary storage: if the transaction fails, it is
+// rolled back along with everything else, so it can never get stuck.
+
+use soroban_sdk::Env;
+
+use crate::DataKey;
+
+pub fn enter(env: &Env, job_id: u64) {
+    let key = DataKey::InFlight(job_id);
+    if env.storage().temporary().has(&key) {
+        panic!("Job is already being processed");
+    }
+    env.storage().temporary().set(&key, &true);
+}
+
+pub fn exit(env: &Env, job_id: u64) {
+    env.storage().temporary().remove(&DataKey::InFlight(job_id));
+}
@@ -0,0 +1,91 @@
+// ----------------------------------------------------------------------
+// PAGED JOB INDEXES
+// ----------------------------------------------------------------------
+// An index is a list of job IDs split into pages of PAGE_SIZE entries.
+// Each job remembers its slot, so removing it moves the last ID into the
+// hole: add and remove touch at most two pages no matter how big the
+// index grows, and nobody can block settlement by spamming jobs into it.
+// The price of O(1) removal is that listing order is not stable.
+
+use soroban_sdk::{contracttype, Env, IntoVal, Val, Vec};
+
+use crate::DataKey;
+
+pub const PAGE_SIZE: u32 = 100;
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Index {
+    Budget(u32), // Funded jobs in a price bucket
+    Expiry(u64), // Funded jobs whose hard deadline falls on a day
+}
+
+pub fn add(env: &Env, index: &Index, job_id: u64) {
+    let len = length(env, index);
+    let page_key = DataKey::IndexPage(index.clone(), len / PAGE_SIZE);
+
+    let mut page: Vec<u64> = env.storage().persistent().get(&page_key).unwrap_or(Vec::new(env));
+    page.push_back(job_id);
+
+    write(env, &page_key, page);
+    write(env, &DataKey::IndexSlot(index.clone(), job_id), len);
+    write(env, &DataKey::IndexLen(index.clone()), len + 1);
+}
+
+// No-op if the job isn't in the index.
+pub fn remove(env: &Env, index: &Index, job_id: u64) {
+    let slot_key = DataKey::IndexSlot(index.clone(), job_id);
+    let Some(slot) = env.storage().persistent().get::<_, u32>(&slot_key) else {
+        return;
+    };
+    let last = length(env, index) - 1;
+
+    // A. Take the last ID off the end
+    let last_key = DataKey::IndexPage(index.clone(), last / PAGE_SIZE);
+    let mut last_page: Vec<u64> = env.storage().persistent().get(&last_key).expect("Index page missing");
+    let moved = last_page.pop_back().expect("Index page empty");
+    if last_page.is_empty() {
+        env.storage().persistent().remove(&last_key);
+    } else {
+        write(env, &last_key, last_page);
+    }
+
+    // B. Put it in the removed job's slot (unless that job WAS the last one)
+    if slot != last {
+        let page_key = DataKey::IndexPage(index.clone(), slot / PAGE_SIZE);
+        let mut page: Vec<u64> = env.storage().persistent().get(&page_key).expect("Index page missing");
+        page.set(slot % PAGE_SIZE, moved);
+        write(env, &page_key, page);
+        write(env, &DataKey::IndexSlot(index.clone(), moved), slot);
+    }
+
+    env.storage().persistent().remove(&slot_key);
+    write(env, &DataKey::IndexLen(index.clone()), last);
+}
+
+pub fn list(env: &Env, index: &Index, offset: u32, limit: u32) -> Vec<u64> {
+    let end = offset.saturating_add(limit).min(length(env, index));
+    let mut ids = Vec::new(env);
+
+    let mut slot = offset;
+    while slot < end {
+        let page_key = DataKey::IndexPage(index.clone(), slot / PAGE_SIZE);
+        let page: Vec<u64> = env.storage().persistent().get(&page_key).unwrap_or(Vec::new(env));
+        let from = slot % PAGE_SIZE;
+        let to = PAGE_SIZE.min(from + (end - slot));
+        for i in from..to {
+            ids.push_back(page.get_unchecked(i));
+        }
+        slot += to - from;
+    }
+    ids
+}
+
+pub fn length(env: &Env, index: &Index) -> u32 {
+    env.storage().persistent().get(&DataKey::IndexLen(index.clone())).unwrap_or(0)
+}
+
+fn write<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: V) {
+    env.storage().persistent().set(key, &value);
+    env.storage().persistent().extend_ttl(key, 17280, 34560);
+}
@@ -1,5 +1,17 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, Symbol};
+// create_escrow takes every negotiated term as an argument. #[contractimpl]
+// mirrors it into the generated FreelanceContractArgs::create_escrow, which
+// doesn't carry fn attributes over, so the allow has to be crate-wide.
+#![allow(clippy::too_many_arguments)]
+use index::Index;
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token, vec, Address, BytesN, Env, IntoVal,
+    String, Symbol, Val, Vec,
+};
+
+mod guard;
+mod index;
+mod test;
 
 // ----------------------------------------------------------------------
 // 1. DATA STRUCTURES
@@ -11,8 +23,26 @@ pub enum JobState {
     Funded = 0,     // Money is locked, work is active
     Completed = 1,  // Work done & Paid
     Cancelled = 2,  // Cancelled (Refunded)
+    ForceMajeure = 3, // Mutually called off (Neutral split, no fault)
 }
 
+// FORCE MAJEURE TERMS
+// Freelancer keeps this share (in basis points) as time compensation,
+// the rest goes back to the Client. Used when create_escrow gets None.
+pub const DEFAULT_FORCE_MAJEURE_COMP_BPS: u32 = 500; // 5%
+
+// SUBSCRIBERS
+// Each one is called on every transition, inside the caller's budget.
+pub const MAX_SUBSCRIBERS: u32 = 5;
+
+// BUDGET BUCKETS (Whole token units, decimals stripped)
+// 0: < 100 | 1: 100 - 1k | 2: 1k - 10k | 3: >= 10k
+pub const AMOUNT_BUCKET_BOUNDS: [i128; 3] = [100, 1_000, 10_000];
+
+// EXPIRY BUCKETS
+// Funded jobs are grouped by hard_deadline / DAY_SECONDS for keepers.
+pub const DAY_SECONDS: u64 = 86_400;
+
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct Job {
@@ -26,13 +56,70 @@ pub struct Job {
     pub hard_deadline: u64,    // Zero payout after this time
     pub penalty_per_sec: i128, // Deduction per second late
     
+    // LEGAL DOCS (NDA, IP assignment...) - hashes of the off-chain files
+    pub agreement_hashes: Vec<BytesN<32>>, // Empty = nothing to sign
+    
+    // FORCE MAJEURE SPLIT - Freelancer's share in basis points
+    pub force_majeure_comp_bps: u32,
+    
+    // BUDGET INDEX - fixed at creation so settlement never asks the token again
+    pub budget_bucket: u32,
+    
     pub state: JobState,
 }
 
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct AgreementAck {
+    pub freelancer: Address,
+    pub hashes: Vec<BytesN<32>>, // Exactly what the Freelancer signed for
+    pub timestamp: u64,          // When they signed
+}
+
+// Written by complete_job: the final record of the engagement, binding
+// the acknowledged legal docs (if any) to how the money was settled.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct CompletionCertificate {
+    pub client: Address,
+    pub freelancer: Address,
+    pub payout: i128,                      // Paid to Freelancer
+    pub refund: i128,                      // Returned to Client
+    pub completed_at: u64,
+    pub agreement_hashes: Vec<BytesN<32>>, // Docs the Freelancer acknowledged (empty = none)
+    pub agreements_acked_at: Option<u64>,  // When they acknowledged them
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub to: Address,
+    pub amount: i128,
+}
+
+// What a settlement WOULD do, returned by the dry_run_* entrypoints.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SettlementPreview {
+    pub ok: bool,                // false = the real call would panic
+    pub error: Option<String>,   // The panic message it would hit
+    pub payout: i128,            // Total to Freelancer
+    pub refund: i128,            // Total to Client
+    pub transfers: Vec<Transfer>, // Every leg, in execution order
+}
+
 #[contracttype]
 pub enum DataKey {
     Job(u64),       // Key: Job ID -> Value: Job Struct
     JobCounter,     // Key: "Counter" -> Value: Total jobs count
+    AgreementAck(u64), // Key: Job ID -> Value: Freelancer's acknowledgment
+    Certificate(u64), // Key: Job ID -> Value: CompletionCertificate
+    Admin,          // Key: "Admin" -> Value: Address allowed to manage config
+    Subscribers,    // Key: "Subscribers" -> Value: Contracts notified of job events
+    IndexLen(Index),        // Key: Index -> Value: Number of job IDs in it
+    IndexPage(Index, u32),  // Key: Index + Page -> Value: Up to PAGE_SIZE job IDs
+    IndexSlot(Index, u64),  // Key: Index + Job ID -> Value: The job's position
+    InFlight(u64),  // Key: Job ID -> Value: true while a lifecycle call runs (temporary)
 }
 
 // ----------------------------------------------------------------------
@@ -45,6 +132,12 @@ pub struct FreelanceContract;
 #[contractimpl]
 impl FreelanceContract {
 
+    // SETUP: Admin is fixed at deploy time (nobody can front-run it)
+    // ----------------------------------------------------------------
+    pub fn __constructor(env: Env, admin: Address) {
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
     // STEP 1: CREATE ESCROW (Lock Money + Set Final Terms)
     // ----------------------------------------------------------------
     // This is called AFTER off-chain negotiation is finished.
@@ -58,6 +151,8 @@ impl FreelanceContract {
         soft_deadline: u64,
         hard_deadline: u64,
         penalty_per_sec: i128,
+        agreement_hashes: Vec<BytesN<32>>,
+        force_majeure_comp_bps: Option<u32>,
     ) -> u64 {
         // A. Security: Client signs to spend money
         client.require_auth();
@@ -69,19 +164,18 @@ impl FreelanceContract {
         if amount <= 0 {
             panic!("Amount must be positive");
         }
+        let force_majeure_comp_bps = force_majeure_comp_bps.unwrap_or(DEFAULT_FORCE_MAJEURE_COMP_BPS);
+        if force_majeure_comp_bps > 10_000 {
+            panic!("Force majeure compensation above 100%");
+        }
 
-        // C. TRANSFER FUNDS (Client -> Contract)
-        // We do this IMMEDIATELY because the agreement is already done.
-        let token_client = token::Client::new(&env, &token);
-        token_client.transfer(
-            &client,
-            &env.current_contract_address(),
-            &amount,
-        );
-
-        // D. Generate ID
+        // C. Reserve ID (BEFORE any call out, so nothing can reuse it)
         let mut count: u64 = env.storage().instance().get(&DataKey::JobCounter).unwrap_or(0);
         count += 1;
+        env.storage().instance().set(&DataKey::JobCounter, &count);
+
+        // D. Price range for the budget index (read-only token call)
+        let bucket = amount_bucket(&env, &token, amount);
 
         // E. Create Job Record
         let new_job = Job {
@@ -92,21 +186,39 @@ impl FreelanceContract {
             soft_deadline,
             hard_deadline,
             penalty_per_sec,
+            agreement_hashes,
+            force_majeure_comp_bps,
+            budget_bucket: bucket,
             state: JobState::Funded, // Starts directly as Funded
         };
 
-        // F. Save & Rent
-        env.storage().instance().set(&DataKey::JobCounter, &count);
+        // F. Save & Rent (BEFORE moving the token)
         env.storage().persistent().set(&DataKey::Job(count), &new_job);
         env.storage().persistent().extend_ttl(&DataKey::Job(count), 17280, 34560);
+        index::add(&env, &Index::Budget(bucket), count);
+        index::add(&env, &Index::Expiry(new_job.hard_deadline / DAY_SECONDS), count);
 
-        return count;
+        // G. TRANSFER FUNDS (Client -> Contract)
+        // We do this IMMEDIATELY because the agreement is already done.
+        let token_client = token::Client::new(&env, &new_job.token);
+        token_client.transfer(
+            &new_job.client,
+            env.current_contract_address(),
+            &amount,
+        );
+
+        // H. Notify Subscribers
+        notify(&env, count, symbol_short!("created"));
+
+        count
     }
 
     // STEP 2: COMPLETE JOB (Calculate Payout based on Time)
     // ----------------------------------------------------------------
     // This handles the math for the deadline penalties.
     pub fn complete_job(env: Env, job_id: u64) {
+        guard::enter(&env, job_id);
+
         // A. Load Job
         let mut job: Job = env.storage().persistent().get(&DataKey::Job(job_id)).expect("Job not found");
 
@@ -114,39 +226,37 @@ impl FreelanceContract {
         // (In a real app, you might want the Freelancer to trigger this if using an Arbiter)
         job.client.require_auth();
 
-        if job.state != JobState::Funded {
-            panic!("Job is not active");
-        }
-
         // C. CALCULATE PAYOUT
-        let current_time = env.ledger().timestamp();
-        let mut payout = job.amount;
-        let mut refund = 0;
-
-        // Scenario 1: On Time
-        if current_time <= job.soft_deadline {
-            payout = job.amount;
-        } 
-        // Scenario 2: Late (Between Soft and Hard)
-        else if current_time < job.hard_deadline {
-            let seconds_late = (current_time - job.soft_deadline) as i128;
-            let penalty = seconds_late * job.penalty_per_sec;
-            
-            if penalty >= job.amount {
-                payout = 0;
-            } else {
-                payout = job.amount - penalty;
-            }
-        } 
-        // Scenario 3: Too Late (After Hard Deadline)
-        else {
-            payout = 0;
-        }
+        let payout = late_payout(&env, &job);
 
         // Calculate Refund (Money saved from penalties goes back to Client)
-        refund = job.amount - payout;
+        let refund = job.amount - payout;
+
+        if let Some(error) = complete_error(&env, job_id, &job, payout) {
+            panic!("{}", error);
+        }
+
+        // D. Close Job & Issue Certificate (BEFORE any money moves)
+        job.state = JobState::Completed;
+        env.storage().persistent().set(&DataKey::Job(job_id), &job);
+        env.storage().persistent().extend_ttl(&DataKey::Job(job_id), 17280, 34560);
+
+        let ack: Option<AgreementAck> = env.storage().persistent().get(&DataKey::AgreementAck(job_id));
+        let certificate = CompletionCertificate {
+            client: job.client.clone(),
+            freelancer: job.freelancer.clone(),
+            payout,
+            refund,
+            completed_at: env.ledger().timestamp(),
+            agreement_hashes: ack.as_ref().map(|a| a.hashes.clone()).unwrap_or(Vec::new(&env)),
+            agreements_acked_at: ack.map(|a| a.timestamp),
+        };
+        env.storage().persistent().set(&DataKey::Certificate(job_id), &certificate);
+        env.storage().persistent().extend_ttl(&DataKey::Certificate(job_id), 17280, 34560);
+        index::remove(&env, &Index::Budget(job.budget_bucket), job_id);
+        index::remove(&env, &Index::Expiry(job.hard_deadline / DAY_SECONDS), job_id);
 
-        // D. EXECUTE TRANSFERS
+        // E. EXECUTE TRANSFERS
         let token_client = token::Client::new(&env, &job.token);
 
         // Pay Freelancer
@@ -167,8 +277,327 @@ impl FreelanceContract {
             );
         }
 
-        // E. Close Job
-        job.state = JobState::Completed;
+        // F. Notify Subscribers
+        notify(&env, job_id, symbol_short!("completed"));
+        guard::exit(&env, job_id);
+    }
+
+    // STEP 3: FORCE MAJEURE (Mutual Handshake, Neutral Outcome)
+    // ----------------------------------------------------------------
+    // Both parties sign this together when the job can't go ahead for
+    // reasons outside anyone's control. The money is split using the
+    // force_majeure_comp_bps agreed at creation and the job is marked
+    // ForceMajeure instead of Cancelled, so nobody's record counts it as
+    // a failure or dispute.
+    pub fn declare_force_majeure(env: Env, job_id: u64) {
+        guard::enter(&env, job_id);
+
+        // A. Load Job
+        let mut job: Job = env.storage().persistent().get(&DataKey::Job(job_id)).expect("Job not found");
+
+        // B. Security: BOTH sides must agree
+        job.client.require_auth();
+        job.freelancer.require_auth();
+
+        if job.state != JobState::Funded {
+            panic!("Job is not active");
+        }
+
+        // C. CALCULATE NEUTRAL SPLIT
+        let (compensation, refund) = force_majeure_split(&job);
+
+        // D. Close Job (No-fault outcome, BEFORE any money moves)
+        job.state = JobState::ForceMajeure;
         env.storage().persistent().set(&DataKey::Job(job_id), &job);
+        env.storage().persistent().extend_ttl(&DataKey::Job(job_id), 17280, 34560);
+        index::remove(&env, &Index::Budget(job.budget_bucket), job_id);
+        index::remove(&env, &Index::Expiry(job.hard_deadline / DAY_SECONDS), job_id);
+
+        // E. EXECUTE TRANSFERS
+        let token_client = token::Client::new(&env, &job.token);
+
+        // Compensate Freelancer for time spent
+        if compensation > 0 {
+            token_client.transfer(
+                &env.current_contract_address(),
+                &job.freelancer,
+                &compensation,
+            );
+        }
+
+        // Refund Client
+        if refund > 0 {
+            token_client.transfer(
+                &env.current_contract_address(),
+                &job.client,
+                &refund,
+            );
+        }
+
+        // F. Notify Subscribers
+        notify(&env, job_id, symbol_short!("fmajeure"));
+        guard::exit(&env, job_id);
     }
-}
\ No newline at end of file
+
+    // STEP 4: ACKNOWLEDGE AGREEMENTS (Freelancer signs the legal docs)
+    // ----------------------------------------------------------------
+    // The Freelancer accepts the job's NDA / IP terms by echoing back the
+    // exact hashes. complete_job won't pay for the work until this is done.
+    // Force-majeure compensation is for time spent, not delivered work, so
+    // it doesn't wait for the ack.
+    pub fn ack_agreements(env: Env, job_id: u64, hashes: Vec<BytesN<32>>) {
+        // A. Load Job
+        let job: Job = env.storage().persistent().get(&DataKey::Job(job_id)).expect("Job not found");
+
+        // B. Security: Freelancer signs
+        job.freelancer.require_auth();
+
+        if job.state != JobState::Funded {
+            panic!("Job is not active");
+        }
+        if job.agreement_hashes.is_empty() {
+            panic!("No agreements to acknowledge");
+        }
+        if hashes != job.agreement_hashes {
+            panic!("Hashes do not match agreements");
+        }
+        if env.storage().persistent().has(&DataKey::AgreementAck(job_id)) {
+            panic!("Agreements already acknowledged");
+        }
+
+        // C. Save Acknowledgment
+        let ack = AgreementAck {
+            freelancer: job.freelancer,
+            hashes,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&DataKey::AgreementAck(job_id), &ack);
+        env.storage().persistent().extend_ttl(&DataKey::AgreementAck(job_id), 17280, 34560);
+    }
+
+    // READ: Acknowledgment record (proof the docs were bound to this job)
+    pub fn get_agreement_ack(env: Env, job_id: u64) -> Option<AgreementAck> {
+        env.storage().persistent().get(&DataKey::AgreementAck(job_id))
+    }
+
+    // READ: Completion certificate (only exists once complete_job ran)
+    pub fn get_certificate(env: Env, job_id: u64) -> Option<CompletionCertificate> {
+        env.storage().persistent().get(&DataKey::Certificate(job_id))
+    }
+
+    // READ: Browse Funded jobs in a price range (see AMOUNT_BUCKET_BOUNDS)
+    // ----------------------------------------------------------------
+    pub fn list_open_jobs_by_budget(env: Env, bucket: u32, offset: u32, limit: u32) -> Vec<u64> {
+        if bucket > AMOUNT_BUCKET_BOUNDS.len() as u32 {
+            panic!("Unknown bucket");
+        }
+
+        index::list(&env, &Index::Budget(bucket), offset, limit)
+    }
+
+    // READ: Funded jobs whose hard deadline falls on a given day
+    // ----------------------------------------------------------------
+    // day_bucket = timestamp / DAY_SECONDS. Keepers walk the buckets up to
+    // today instead of scanning every job ID.
+    pub fn list_expiring(env: Env, day_bucket: u64, offset: u32, limit: u32) -> Vec<u64> {
+        index::list(&env, &Index::Expiry(day_bucket), offset, limit)
+    }
+
+    // SIMULATION: Preview complete_job without moving anything
+    // ----------------------------------------------------------------
+    // Runs the same checks and payout math as complete_job and returns
+    // the result instead of panicking. Auth is skipped (nothing is signed
+    // in a preview), but the escrow's token balance is checked since the
+    // real transfers would fail without it. Meant to be called through
+    // simulateTransaction.
+    pub fn dry_run_complete(env: Env, job_id: u64) -> SettlementPreview {
+        let job = match load_for_preview(&env, job_id) {
+            Ok(job) => job,
+            Err(error) => return failed_preview(&env, error),
+        };
+        let payout = late_payout(&env, &job);
+        if let Some(error) = complete_error(&env, job_id, &job, payout) {
+            return failed_preview(&env, error);
+        }
+
+        settlement_preview(&env, &job, payout, job.amount - payout)
+    }
+
+    // SIMULATION: Preview cancelling a job without moving anything
+    // ----------------------------------------------------------------
+    // Same rules as dry_run_complete. The only way to cancel a Funded job
+    // is the mutual force-majeure handshake, so this previews the
+    // declare_force_majeure unwind.
+    pub fn dry_run_cancel(env: Env, job_id: u64) -> SettlementPreview {
+        let job = match load_for_preview(&env, job_id) {
+            Ok(job) => job,
+            Err(error) => return failed_preview(&env, error),
+        };
+        if job.state != JobState::Funded {
+            return failed_preview(&env, "Job is not active");
+        }
+
+        let (compensation, refund) = force_majeure_split(&job);
+        settlement_preview(&env, &job, compensation, refund)
+    }
+
+    // ADMIN: Register a contract to receive on_job_event(job_id, kind)
+    // ----------------------------------------------------------------
+    // Kinds: "created", "completed", "fmajeure".
+    // Callbacks run inside the caller's budget and Soroban can't cap the
+    // budget of a single sub-call, so only register cheap, vetted
+    // contracts: one that burns the whole budget fails the transition.
+    pub fn add_subscriber(env: Env, subscriber: Address) {
+        require_admin(&env);
+
+        let mut subs: Vec<Address> = env.storage().instance().get(&DataKey::Subscribers).unwrap_or(Vec::new(&env));
+        if subs.contains(&subscriber) {
+            panic!("Already subscribed");
+        }
+        if subs.len() >= MAX_SUBSCRIBERS {
+            panic!("Too many subscribers");
+        }
+        subs.push_back(subscriber);
+        env.storage().instance().set(&DataKey::Subscribers, &subs);
+    }
+
+    // ADMIN: Stop notifying a contract
+    pub fn remove_subscriber(env: Env, subscriber: Address) {
+        require_admin(&env);
+
+        let mut subs: Vec<Address> = env.storage().instance().get(&DataKey::Subscribers).unwrap_or(Vec::new(&env));
+        let index = subs.first_index_of(&subscriber).expect("Not subscribed");
+        subs.remove(index);
+        env.storage().instance().set(&DataKey::Subscribers, &subs);
+    }
+
+    // READ: Current subscriber list
+    pub fn get_subscribers(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&DataKey::Subscribers).unwrap_or(Vec::new(&env))
+    }
+}
+
+// ----------------------------------------------------------------------
+// 3. HELPERS
+// ----------------------------------------------------------------------
+
+fn require_admin(env: &Env) {
+    let admin: Address = env.storage().instance().get(&DataKey::Admin).expect("Admin not set");
+    admin.require_auth();
+}
+
+// Checks complete_job runs after auth (shared with dry_run_complete).
+// An unacknowledged job can still be settled when the Freelancer gets
+// nothing (e.g. after the hard deadline), so a missing ack never traps
+// the Client's refund.
+fn complete_error(env: &Env, job_id: u64, job: &Job, payout: i128) -> Option<&'static str> {
+    if job.state != JobState::Funded {
+        return Some("Job is not active");
+    }
+    if payout > 0
+        && !job.agreement_hashes.is_empty()
+        && !env.storage().persistent().has(&DataKey::AgreementAck(job_id))
+    {
+        return Some("Agreements not acknowledged");
+    }
+    None
+}
+
+// Freelancer's payout at the current time, after late penalties.
+fn late_payout(env: &Env, job: &Job) -> i128 {
+    let current_time = env.ledger().timestamp();
+
+    // Scenario 1: On Time
+    if current_time <= job.soft_deadline {
+        job.amount
+    }
+    // Scenario 2: Late (Between Soft and Hard)
+    else if current_time < job.hard_deadline {
+        let seconds_late = (current_time - job.soft_deadline) as i128;
+        let penalty = seconds_late * job.penalty_per_sec;
+
+        if penalty >= job.amount {
+            0
+        } else {
+            job.amount - penalty
+        }
+    }
+    // Scenario 3: Too Late (After Hard Deadline)
+    else {
+        0
+    }
+}
+
+// (Freelancer compensation, Client refund) for a force-majeure exit.
+fn force_majeure_split(job: &Job) -> (i128, i128) {
+    let compensation = job.amount * job.force_majeure_comp_bps as i128 / 10_000;
+    (compensation, job.amount - compensation)
+}
+
+// Load a job for a dry run, failing the same way a real call would.
+fn load_for_preview(env: &Env, job_id: u64) -> Result<Job, &'static str> {
+    env.storage().persistent().get(&DataKey::Job(job_id)).ok_or("Job not found")
+}
+
+fn failed_preview(env: &Env, error: &str) -> SettlementPreview {
+    SettlementPreview {
+        ok: false,
+        error: Some(String::from_str(env, error)),
+        payout: 0,
+        refund: 0,
+        transfers: Vec::new(env),
+    }
+}
+
+// Same legs, same order as the real settlement (Freelancer first, then Client).
+fn settlement_preview(env: &Env, job: &Job, payout: i128, refund: i128) -> SettlementPreview {
+    let balance = token::Client::new(env, &job.token).balance(&env.current_contract_address());
+    if balance < payout + refund {
+        return failed_preview(env, "Insufficient escrow balance");
+    }
+
+    let mut transfers = Vec::new(env);
+    if payout > 0 {
+        transfers.push_back(Transfer { to: job.freelancer.clone(), amount: payout });
+    }
+    if refund > 0 {
+        transfers.push_back(Transfer { to: job.client.clone(), amount: refund });
+    }
+
+    SettlementPreview {
+        ok: true,
+        error: None,
+        payout,
+        refund,
+        transfers,
+    }
+}
+
+// Which budget bucket an amount falls in, after stripping token decimals.
+fn amount_bucket(env: &Env, token: &Address, amount: i128) -> u32 {
+    let decimals = token::Client::new(env, token).decimals();
+    let unit = 10i128.checked_pow(decimals).expect("Unsupported token decimals");
+    let whole_units = amount / unit;
+
+    let mut bucket = 0;
+    for bound in AMOUNT_BUCKET_BOUNDS {
+        if whole_units >= bound {
+            bucket += 1;
+        }
+    }
+    bucket
+}
+
+// Best-effort callback to every subscriber. A failing (or missing)
+// subscriber must never block a job transition, so errors are dropped.
+// Running out of budget can't be caught, which is why the list is capped
+// at MAX_SUBSCRIBERS and admin-curated.
+fn notify(env: &Env, job_id: u64, kind: Symbol) {
+    let subs: Vec<Address> = env.storage().instance().get(&DataKey::Subscribers).unwrap_or(Vec::new(env));
+    let func = Symbol::new(env, "on_job_event");
+
+    for sub in subs.iter() {
+        let args: Vec<Val> = vec![env, job_id.into_val(env), kind.into_val(env)];
+        let _ = env.try_invoke_contract::<Val, soroban_sdk::Error>(&sub, &func, args);
+    }
+}
@@ -1,21 +1,819 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    vec, BytesN, Env, IntoVal, String, Symbol, Val, Vec,
+};
+
+// ----------------------------------------------------------------------
+// SETUP
+// ----------------------------------------------------------------------
+
+const AMOUNT: i128 = 10_000_000_000; // 1,000 units of a 7-decimal token
+const SOFT_DEADLINE: u64 = 1_000;
+const HARD_DEADLINE: u64 = 2_000;
+const PENALTY_PER_SEC: i128 = 1_000_000;
+
+struct Setup<'a> {
+    escrow: FreelanceContractClient<'a>,
+    admin: Address,
+    token: TokenClient<'a>,
+    client: Address,
+    freelancer: Address,
+}
+
+fn setup(env: &Env) -> Setup<'_> {
+    env.mock_all_auths();
+
+    let admin = Address::generate(env);
+    let escrow_id = env.register(FreelanceContract, (&admin,));
+    let issuer = Address::generate(env);
+    let token_id = env.register_stellar_asset_contract_v2(issuer).address();
+
+    let client = Address::generate(env);
+    let freelancer = Address::generate(env);
+    StellarAssetClient::new(env, &token_id).mint(&client, &(AMOUNT * 10));
+
+    Setup {
+        escrow: FreelanceContractClient::new(env, &escrow_id),
+        admin,
+        token: TokenClient::new(env, &token_id),
+        client,
+        freelancer,
+    }
+}
+
+fn create_job(s: &Setup, amount: i128) -> u64 {
+    create_job_with(s, amount, Vec::new(&s.escrow.env), None)
+}
+
+fn create_job_with(
+    s: &Setup,
+    amount: i128,
+    agreement_hashes: Vec<BytesN<32>>,
+    force_majeure_comp_bps: Option<u32>,
+) -> u64 {
+    s.escrow.create_escrow(
+        &s.client,
+        &s.freelancer,
+        &s.token.address,
+        &amount,
+        &SOFT_DEADLINE,
+        &HARD_DEADLINE,
+        &PENALTY_PER_SEC,
+        &agreement_hashes,
+        &force_majeure_comp_bps,
+    )
+}
+
+fn job_state(env: &Env, escrow: &Address, job_id: u64) -> JobState {
+    env.as_contract(escrow, || {
+        let job: Job = env.storage().persistent().get(&DataKey::Job(job_id)).unwrap();
+        job.state
+    })
+}
+
+// ----------------------------------------------------------------------
+// CREATE & COMPLETE
+// ----------------------------------------------------------------------
+
+#[test]
+fn test_create_locks_funds() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    let job_id = create_job(&s, AMOUNT);
+
+    assert_eq!(job_id, 1);
+    assert_eq!(s.token.balance(&s.escrow.address), AMOUNT);
+    assert_eq!(job_state(&env, &s.escrow.address, job_id), JobState::Funded);
+}
+
+#[test]
+fn test_complete_on_time_pays_in_full() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.complete_job(&job_id);
+
+    assert_eq!(s.token.balance(&s.freelancer), AMOUNT);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+    assert_eq!(job_state(&env, &s.escrow.address, job_id), JobState::Completed);
+}
+
+#[test]
+fn test_complete_late_applies_penalty() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+    env.ledger().set_timestamp(SOFT_DEADLINE + 100);
+
+    s.escrow.complete_job(&job_id);
+
+    let penalty = 100 * PENALTY_PER_SEC;
+    assert_eq!(s.token.balance(&s.freelancer), AMOUNT - penalty);
+    assert_eq!(s.token.balance(&s.client), AMOUNT * 9 + penalty);
+}
+
+#[test]
+#[should_panic(expected = "Job is not active")]
+fn test_complete_twice_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.complete_job(&job_id);
+    s.escrow.complete_job(&job_id);
+}
+
+// ----------------------------------------------------------------------
+// FORCE MAJEURE
+// ----------------------------------------------------------------------
+
+#[test]
+fn test_force_majeure_default_split() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.declare_force_majeure(&job_id);
+
+    let compensation = AMOUNT * DEFAULT_FORCE_MAJEURE_COMP_BPS as i128 / 10_000;
+    assert_eq!(s.token.balance(&s.freelancer), compensation);
+    assert_eq!(s.token.balance(&s.client), AMOUNT * 10 - compensation);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+    assert_eq!(job_state(&env, &s.escrow.address, job_id), JobState::ForceMajeure);
+}
+
+#[test]
+fn test_force_majeure_negotiated_split() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, Vec::new(&env), Some(2_500));
+
+    s.escrow.declare_force_majeure(&job_id);
+
+    assert_eq!(s.token.balance(&s.freelancer), AMOUNT / 4);
+    assert_eq!(s.token.balance(&s.client), AMOUNT * 10 - AMOUNT / 4);
+}
+
+#[test]
+fn test_force_majeure_requires_both_signatures() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.declare_force_majeure(&job_id);
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 2);
+    assert!(auths.iter().any(|(signer, _)| *signer == s.client));
+    assert!(auths.iter().any(|(signer, _)| *signer == s.freelancer));
+}
+
+#[test]
+#[should_panic(expected = "Force majeure compensation above 100%")]
+fn test_force_majeure_comp_above_100_percent_rejected() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    create_job_with(&s, AMOUNT, Vec::new(&env), Some(10_001));
+}
+
+#[test]
+#[should_panic(expected = "Job is not active")]
+fn test_force_majeure_after_complete_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.complete_job(&job_id);
+    s.escrow.declare_force_majeure(&job_id);
+}
+
+// ----------------------------------------------------------------------
+// AGREEMENTS
+// ----------------------------------------------------------------------
+
+fn agreements(env: &Env) -> Vec<BytesN<32>> {
+    vec![env, BytesN::from_array(env, &[1; 32]), BytesN::from_array(env, &[2; 32])]
+}
+
+#[test]
+fn test_ack_then_complete_issues_certificate() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+
+    s.escrow.ack_agreements(&job_id, &agreements(&env));
+    s.escrow.complete_job(&job_id);
+
+    let ack = s.escrow.get_agreement_ack(&job_id).unwrap();
+    assert_eq!(ack.freelancer, s.freelancer);
+    assert_eq!(ack.hashes, agreements(&env));
+
+    let certificate = s.escrow.get_certificate(&job_id).unwrap();
+    assert_eq!(certificate.payout, AMOUNT);
+    assert_eq!(certificate.refund, 0);
+    assert_eq!(certificate.agreement_hashes, agreements(&env));
+    assert_eq!(certificate.agreements_acked_at, Some(ack.timestamp));
+}
+
+#[test]
+#[should_panic(expected = "Agreements not acknowledged")]
+fn test_complete_without_ack_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+
+    s.escrow.complete_job(&job_id);
+}
+
+#[test]
+fn test_complete_without_ack_after_hard_deadline_refunds_client() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+    env.ledger().set_timestamp(HARD_DEADLINE);
+
+    s.escrow.complete_job(&job_id);
+
+    assert_eq!(s.token.balance(&s.client), AMOUNT * 10);
+    assert_eq!(s.token.balance(&s.freelancer), 0);
+    let certificate = s.escrow.get_certificate(&job_id).unwrap();
+    assert!(certificate.agreement_hashes.is_empty());
+    assert_eq!(certificate.agreements_acked_at, None);
+}
+
+#[test]
+#[should_panic(expected = "Hashes do not match agreements")]
+fn test_ack_with_wrong_hashes_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+
+    let wrong = vec![&env, BytesN::from_array(&env, &[9; 32])];
+    s.escrow.ack_agreements(&job_id, &wrong);
+}
+
+#[test]
+#[should_panic(expected = "Agreements already acknowledged")]
+fn test_double_ack_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+
+    s.escrow.ack_agreements(&job_id, &agreements(&env));
+    s.escrow.ack_agreements(&job_id, &agreements(&env));
+}
+
+#[test]
+#[should_panic(expected = "No agreements to acknowledge")]
+fn test_ack_without_agreements_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.ack_agreements(&job_id, &Vec::new(&env));
+}
+
+#[test]
+fn test_force_majeure_without_ack_still_compensates() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, agreements(&env), None);
+
+    s.escrow.declare_force_majeure(&job_id);
+
+    let compensation = AMOUNT * DEFAULT_FORCE_MAJEURE_COMP_BPS as i128 / 10_000;
+    assert_eq!(s.token.balance(&s.freelancer), compensation);
+    assert!(s.escrow.get_certificate(&job_id).is_none());
+}
+
+// ----------------------------------------------------------------------
+// BUDGET INDEX
+// ----------------------------------------------------------------------
+
+const UNIT: i128 = 10_000_000; // 1 unit of a 7-decimal token
+
+#[test]
+fn test_jobs_indexed_by_budget_bucket() {
+    let env = Env::default();
+    let s = setup(&env);
+    StellarAssetClient::new(&env, &s.token.address).mint(&s.client, &(60_000 * UNIT));
+
+    let small = create_job(&s, 50 * UNIT);
+    let medium = create_job(&s, 500 * UNIT);
+    let large = create_job(&s, 5_000 * UNIT);
+    let huge = create_job(&s, 50_000 * UNIT);
+
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &0, &10), vec![&env, small]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&1, &0, &10), vec![&env, medium]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&2, &0, &10), vec![&env, large]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&3, &0, &10), vec![&env, huge]);
+}
+
+#[test]
+fn test_budget_listing_pages_and_drops_settled_jobs() {
+    let env = Env::default();
+    let s = setup(&env);
+    let first = create_job(&s, UNIT);
+    let second = create_job(&s, UNIT);
+    let third = create_job(&s, UNIT);
+
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &1, &1), vec![&env, second]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &2, &10), vec![&env, third]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &5, &10).len(), 0);
+
+    s.escrow.complete_job(&first);
+    s.escrow.declare_force_majeure(&second);
+
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &0, &10), vec![&env, third]);
+}
+
+#[test]
+fn test_bucket_bounds_are_inclusive() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    let just_below = create_job(&s, 100 * UNIT - 1);
+    let on_bound = create_job(&s, 100 * UNIT);
+
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&0, &0, &10), vec![&env, just_below]);
+    assert_eq!(s.escrow.list_open_jobs_by_budget(&1, &0, &10), vec![&env, on_bound]);
+}
+
+#[test]
+#[should_panic(expected = "Unknown bucket")]
+fn test_unknown_budget_bucket_fails() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    s.escrow.list_open_jobs_by_budget(&(AMOUNT_BUCKET_BOUNDS.len() as u32 + 1), &0, &10);
+}
+
+#[test]
+fn test_index_remove_swaps_last_into_hole() {
+    let env = Env::default();
+    let s = setup(&env);
+    let budget = Index::Budget(0);
+    let count = index::PAGE_SIZE as u64 * 2 + 5;
+
+    for job_id in 0..count {
+        env.as_contract(&s.escrow.address, || index::add(&env, &budget, job_id));
+    }
+
+    env.as_contract(&s.escrow.address, || {
+        // Removing from the first page moves the last ID into the hole
+        index::remove(&env, &budget, 3);
+        assert_eq!(index::length(&env, &budget), count as u32 - 1);
+        assert_eq!(index::list(&env, &budget, 3, 1), vec![&env, count - 1]);
+
+        // The moved ID can be removed again from its new slot
+        index::remove(&env, &budget, count - 1);
+        assert_eq!(index::list(&env, &budget, 3, 1), vec![&env, count - 2]);
+
+        // Removing an unknown ID is a no-op
+        index::remove(&env, &budget, 3);
+        assert_eq!(index::length(&env, &budget), count as u32 - 2);
+
+        let ids = index::list(&env, &budget, 0, u32::MAX);
+        assert_eq!(ids.len(), count as u32 - 2);
+        assert!(!ids.contains(3));
+        assert!(!ids.contains(count - 1));
+    });
+}
+
+#[contract]
+pub struct HugeDecimalsToken;
+
+#[contractimpl]
+impl HugeDecimalsToken {
+    pub fn decimals(_env: Env) -> u32 {
+        39
+    }
+
+    pub fn transfer(_env: Env, _from: Address, _to: Address, _amount: i128) {}
+}
 
 #[test]
-fn test() {
+#[should_panic(expected = "Unsupported token decimals")]
+fn test_token_with_overflowing_decimals_rejected() {
     let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
+    let s = setup(&env);
+    let token_id = env.register(HugeDecimalsToken, ());
+
+    s.escrow.create_escrow(
+        &s.client,
+        &s.freelancer,
+        &token_id,
+        &AMOUNT,
+        &SOFT_DEADLINE,
+        &HARD_DEADLINE,
+        &PENALTY_PER_SEC,
+        &Vec::new(&env),
+        &None,
+    );
+}
+
+// ----------------------------------------------------------------------
+// EXPIRY INDEX
+// ----------------------------------------------------------------------
+
+fn create_job_due(s: &Setup, hard_deadline: u64) -> u64 {
+    s.escrow.create_escrow(
+        &s.client,
+        &s.freelancer,
+        &s.token.address,
+        &UNIT,
+        &(hard_deadline - 1),
+        &hard_deadline,
+        &PENALTY_PER_SEC,
+        &Vec::new(&s.escrow.env),
+        &None,
+    )
+}
+
+#[test]
+fn test_jobs_indexed_by_hard_deadline_day() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    let today = create_job_due(&s, 10);
+    let also_today = create_job_due(&s, DAY_SECONDS - 1);
+    let tomorrow = create_job_due(&s, DAY_SECONDS);
+    let later = create_job_due(&s, DAY_SECONDS * 5 + 7);
+
+    assert_eq!(s.escrow.list_expiring(&0, &0, &10), vec![&env, today, also_today]);
+    assert_eq!(s.escrow.list_expiring(&1, &0, &10), vec![&env, tomorrow]);
+    assert_eq!(s.escrow.list_expiring(&5, &0, &10), vec![&env, later]);
+    assert_eq!(s.escrow.list_expiring(&2, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_expiring_listing_pages_and_drops_settled_jobs() {
+    let env = Env::default();
+    let s = setup(&env);
+    let first = create_job_due(&s, 100);
+    let second = create_job_due(&s, 200);
+    let third = create_job_due(&s, 300);
+
+    assert_eq!(s.escrow.list_expiring(&0, &0, &2), vec![&env, first, second]);
+    assert_eq!(s.escrow.list_expiring(&0, &2, &2), vec![&env, third]);
+
+    s.escrow.complete_job(&first);
+    s.escrow.declare_force_majeure(&third);
+
+    assert_eq!(s.escrow.list_expiring(&0, &0, &10), vec![&env, second]);
+}
+
+// ----------------------------------------------------------------------
+// SUBSCRIBERS
+// ----------------------------------------------------------------------
+
+#[contract]
+pub struct RecordingSubscriber;
+
+#[contractimpl]
+impl RecordingSubscriber {
+    pub fn on_job_event(env: Env, job_id: u64, kind: Symbol) {
+        let mut events: Vec<(u64, Symbol)> = env.storage().instance().get(&0u32).unwrap_or(Vec::new(&env));
+        events.push_back((job_id, kind));
+        env.storage().instance().set(&0u32, &events);
+    }
+
+    pub fn events(env: Env) -> Vec<(u64, Symbol)> {
+        env.storage().instance().get(&0u32).unwrap_or(Vec::new(&env))
+    }
+}
 
-    let words = client.hello(&String::from_str(&env, "Dev"));
+#[contract]
+pub struct FailingSubscriber;
+
+#[contractimpl]
+impl FailingSubscriber {
+    pub fn on_job_event(_env: Env, _job_id: u64, _kind: Symbol) {
+        panic!("subscriber is broken");
+    }
+}
+
+#[test]
+fn test_subscribers_receive_job_events() {
+    let env = Env::default();
+    let s = setup(&env);
+    let sub_id = env.register(RecordingSubscriber, ());
+    s.escrow.add_subscriber(&sub_id);
+
+    let job_id = create_job(&s, AMOUNT);
+    s.escrow.complete_job(&job_id);
+
+    let events = RecordingSubscriberClient::new(&env, &sub_id).events();
     assert_eq!(
-        words,
+        events,
         vec![
             &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
+            (job_id, symbol_short!("created")),
+            (job_id, symbol_short!("completed")),
         ]
     );
 }
+
+#[test]
+fn test_failing_subscriber_does_not_block_transitions() {
+    let env = Env::default();
+    let s = setup(&env);
+    let broken_id = env.register(FailingSubscriber, ());
+    let sub_id = env.register(RecordingSubscriber, ());
+    s.escrow.add_subscriber(&broken_id);
+    s.escrow.add_subscriber(&sub_id);
+
+    let job_id = create_job(&s, AMOUNT);
+    s.escrow.declare_force_majeure(&job_id);
+
+    assert_eq!(job_state(&env, &s.escrow.address, job_id), JobState::ForceMajeure);
+    assert_eq!(RecordingSubscriberClient::new(&env, &sub_id).events().len(), 2);
+}
+
+#[test]
+fn test_add_subscriber_requires_admin() {
+    let env = Env::default();
+    let s = setup(&env);
+    let sub_id = env.register(RecordingSubscriber, ());
+
+    s.escrow.add_subscriber(&sub_id);
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, s.admin);
+}
+
+#[test]
+#[should_panic(expected = "Too many subscribers")]
+fn test_subscriber_list_is_capped() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    for _ in 0..=MAX_SUBSCRIBERS {
+        s.escrow.add_subscriber(&Address::generate(&env));
+    }
+}
+
+#[test]
+fn test_remove_subscriber() {
+    let env = Env::default();
+    let s = setup(&env);
+    let sub_id = env.register(RecordingSubscriber, ());
+    s.escrow.add_subscriber(&sub_id);
+
+    s.escrow.remove_subscriber(&sub_id);
+    create_job(&s, AMOUNT);
+
+    assert_eq!(s.escrow.get_subscribers().len(), 0);
+    assert_eq!(RecordingSubscriberClient::new(&env, &sub_id).events().len(), 0);
+}
+
+// ----------------------------------------------------------------------
+// DRY RUNS
+// ----------------------------------------------------------------------
+
+// Every previewed leg must show up as exactly that balance change.
+fn assert_legs_match(s: &Setup, preview: &SettlementPreview, freelancer_before: i128, client_before: i128) {
+    let mut freelancer_gain = 0;
+    let mut client_gain = 0;
+    for leg in preview.transfers.iter() {
+        if leg.to == s.freelancer {
+            freelancer_gain += leg.amount;
+        } else if leg.to == s.client {
+            client_gain += leg.amount;
+        } else {
+            panic!("unexpected transfer target");
+        }
+    }
+    assert_eq!(s.token.balance(&s.freelancer) - freelancer_before, freelancer_gain);
+    assert_eq!(s.token.balance(&s.client) - client_before, client_gain);
+}
+
+#[test]
+fn test_dry_run_complete_matches_real_settlement() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+    env.ledger().set_timestamp(SOFT_DEADLINE + 250);
+
+    let preview = s.escrow.dry_run_complete(&job_id);
+    assert!(preview.ok);
+    assert_eq!(preview.error, None);
+    assert_eq!(preview.payout, AMOUNT - 250 * PENALTY_PER_SEC);
+    assert_eq!(preview.refund, 250 * PENALTY_PER_SEC);
+    assert_eq!(preview.transfers.len(), 2);
+    assert_eq!(job_state(&env, &s.escrow.address, job_id), JobState::Funded);
+
+    let (freelancer_before, client_before) = (s.token.balance(&s.freelancer), s.token.balance(&s.client));
+    s.escrow.complete_job(&job_id);
+    assert_legs_match(&s, &preview, freelancer_before, client_before);
+}
+
+#[test]
+fn test_dry_run_cancel_matches_force_majeure() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job_with(&s, AMOUNT, Vec::new(&env), Some(1_000));
+
+    let preview = s.escrow.dry_run_cancel(&job_id);
+    assert!(preview.ok);
+    assert_eq!(preview.payout, AMOUNT / 10);
+    assert_eq!(preview.refund, AMOUNT - AMOUNT / 10);
+
+    let (freelancer_before, client_before) = (s.token.balance(&s.freelancer), s.token.balance(&s.client));
+    s.escrow.declare_force_majeure(&job_id);
+    assert_legs_match(&s, &preview, freelancer_before, client_before);
+}
+
+#[test]
+fn test_dry_run_reports_errors_instead_of_panicking() {
+    let env = Env::default();
+    let s = setup(&env);
+    let unacked = create_job_with(&s, AMOUNT, vec![&env, BytesN::from_array(&env, &[1; 32])], None);
+    let done = create_job(&s, AMOUNT);
+    s.escrow.complete_job(&done);
+
+    let error = |preview: SettlementPreview| {
+        assert!(!preview.ok);
+        assert_eq!(preview.transfers.len(), 0);
+        preview.error.unwrap()
+    };
+    assert_eq!(error(s.escrow.dry_run_complete(&99)), String::from_str(&env, "Job not found"));
+    assert_eq!(error(s.escrow.dry_run_complete(&done)), String::from_str(&env, "Job is not active"));
+    assert_eq!(error(s.escrow.dry_run_cancel(&done)), String::from_str(&env, "Job is not active"));
+    assert_eq!(error(s.escrow.dry_run_complete(&unacked)), String::from_str(&env, "Agreements not acknowledged"));
+}
+
+#[test]
+fn test_dry_run_checks_escrow_balance() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+    s.token.transfer(&s.escrow.address, &s.client, &1);
+
+    let preview = s.escrow.dry_run_complete(&job_id);
+
+    assert!(!preview.ok);
+    assert_eq!(preview.error, Some(String::from_str(&env, "Insufficient escrow balance")));
+    assert!(s.escrow.try_complete_job(&job_id).is_err());
+}
+
+// ----------------------------------------------------------------------
+// REENTRANCY GUARD
+// ----------------------------------------------------------------------
+
+#[test]
+#[should_panic(expected = "Job is already being processed")]
+fn test_guard_rejects_nested_entry() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    env.as_contract(&s.escrow.address, || {
+        guard::enter(&env, 1);
+        guard::enter(&env, 1);
+    });
+}
+
+#[test]
+fn test_guard_is_per_job_and_released_on_exit() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    env.as_contract(&s.escrow.address, || {
+        guard::enter(&env, 1);
+        guard::enter(&env, 2);
+        guard::exit(&env, 1);
+        guard::enter(&env, 1);
+    });
+}
+
+#[test]
+fn test_guard_released_after_settlement() {
+    let env = Env::default();
+    let s = setup(&env);
+    let job_id = create_job(&s, AMOUNT);
+
+    s.escrow.complete_job(&job_id);
+
+    env.as_contract(&s.escrow.address, || {
+        assert!(!env.storage().temporary().has(&DataKey::InFlight(job_id)));
+    });
+}
+
+// ----------------------------------------------------------------------
+// HOSTILE TOKEN
+// ----------------------------------------------------------------------
+// Pretends to be a token, but when the escrow pays out it tries to call
+// back into the escrow for the same job and records whether it got in.
+// The host refuses that call before it ever reaches guard.rs, so these
+// tests only show that settlement still finishes with the job closed and
+// the indexes clean. The guard is covered by the tests above.
+
+#[contracttype]
+enum MockKey {
+    Target,   // Escrow contract to attack
+    Entry,    // Function to re-enter
+    JobId,    // Job to re-enter on
+    Attempted,
+    Blocked,
+}
+
+#[contract]
+pub struct MaliciousToken;
+
+#[contractimpl]
+impl MaliciousToken {
+    pub fn arm(env: Env, target: Address, entry: Symbol, job_id: u64) {
+        env.storage().instance().set(&MockKey::Target, &target);
+        env.storage().instance().set(&MockKey::Entry, &entry);
+        env.storage().instance().set(&MockKey::JobId, &job_id);
+    }
+
+    pub fn decimals(_env: Env) -> u32 {
+        7
+    }
+
+    pub fn transfer(env: Env, from: Address, _to: Address, _amount: i128) {
+        let target: Option<Address> = env.storage().instance().get(&MockKey::Target);
+        let Some(target) = target else { return };
+        if from != target {
+            return;
+        }
+
+        let entry: Symbol = env.storage().instance().get(&MockKey::Entry).unwrap();
+        let job_id: u64 = env.storage().instance().get(&MockKey::JobId).unwrap();
+        let args: Vec<Val> = vec![&env, job_id.into_val(&env)];
+        let result = env.try_invoke_contract::<Val, soroban_sdk::Error>(&target, &entry, args);
+
+        env.storage().instance().set(&MockKey::Attempted, &true);
+        env.storage().instance().set(&MockKey::Blocked, &result.is_err());
+    }
+
+    pub fn attempted(env: Env) -> bool {
+        env.storage().instance().get(&MockKey::Attempted).unwrap_or(false)
+    }
+
+    pub fn blocked(env: Env) -> bool {
+        env.storage().instance().get(&MockKey::Blocked).unwrap_or(false)
+    }
+}
+
+fn setup_malicious(env: &Env) -> (FreelanceContractClient<'_>, MaliciousTokenClient<'_>, u64) {
+    env.mock_all_auths();
+
+    let escrow_id = env.register(FreelanceContract, (Address::generate(env),));
+    let token_id = env.register(MaliciousToken, ());
+    let escrow = FreelanceContractClient::new(env, &escrow_id);
+    let token = MaliciousTokenClient::new(env, &token_id);
+
+    let client = Address::generate(env);
+    let freelancer = Address::generate(env);
+    let job_id = escrow.create_escrow(
+        &client,
+        &freelancer,
+        &token_id,
+        &AMOUNT,
+        &SOFT_DEADLINE,
+        &HARD_DEADLINE,
+        &PENALTY_PER_SEC,
+        &Vec::new(env),
+        &None,
+    );
+
+    (escrow, token, job_id)
+}
+
+#[test]
+fn test_hostile_token_cannot_disrupt_complete() {
+    let env = Env::default();
+    let (escrow, token, job_id) = setup_malicious(&env);
+    token.arm(&escrow.address, &Symbol::new(&env, "complete_job"), &job_id);
+
+    escrow.complete_job(&job_id);
+
+    assert!(token.attempted());
+    assert!(token.blocked());
+    assert_eq!(job_state(&env, &escrow.address, job_id), JobState::Completed);
+    assert_eq!(escrow.list_open_jobs_by_budget(&2, &0, &10).len(), 0);
+    assert_eq!(escrow.list_expiring(&0, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_hostile_token_cannot_disrupt_force_majeure() {
+    let env = Env::default();
+    let (escrow, token, job_id) = setup_malicious(&env);
+    token.arm(&escrow.address, &Symbol::new(&env, "declare_force_majeure"), &job_id);
+
+    escrow.declare_force_majeure(&job_id);
+
+    assert!(token.attempted());
+    assert!(token.blocked());
+    assert_eq!(job_state(&env, &escrow.address, job_id), JobState::ForceMajeure);
+}